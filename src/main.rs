@@ -1,11 +1,22 @@
 #![feature(result_flattening)]
 extern crate log;
 
+mod extrinsic;
+mod registry;
+mod scheduler;
+mod sources;
+mod types;
+
+use extrinsic::{compose_signed_extrinsic, ExtrinsicParams, PairSigner, SignExtrinsic};
+use registry::CustomTypeRegistry;
+
 use sp_core::crypto::Pair;
 use sp_runtime::MultiSignature;
 use std::{
     convert::TryFrom,
-    sync::mpsc::{channel, Receiver},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use keyring::AccountKeyring;
@@ -14,17 +25,16 @@ use codec::Decode;
 use node_primitives::AccountId;
 use substrate_api_client::node_metadata::Metadata;
 use substrate_api_client::{
-    compose_extrinsic,
+    compose_call, compose_extrinsic,
     events::{EventsDecoder, RawEvent, RuntimeEvent},
     extrinsic::xt_primitives::*,
     utils::hexstr_to_vec,
     Api,
 };
 
-pub type AssetId = u32;
-pub type OracleId = u32;
 pub type Moment = u64;
-pub type CallIndex = [u8; 2];
+
+pub use types::{AssetId, CallIndex, OracleId};
 
 pub type CreateOracleFn = (
     CallIndex,
@@ -38,13 +48,35 @@ pub type CreateOracleFn = (
 
 pub type CreateOracleXt = UncheckedExtrinsicV4<CreateOracleFn>;
 
+pub type PushValueFn = (CallIndex, OracleId, u8, u128, Moment);
+pub type PushValueXt = UncheckedExtrinsicV4<PushValueFn>;
+
+pub type CommitValueFn = (CallIndex, OracleId);
+pub type CommitValueXt = UncheckedExtrinsicV4<CommitValueFn>;
+
+pub type RegisterSourceFn = (CallIndex, OracleId);
+pub type RegisterSourceXt = UncheckedExtrinsicV4<RegisterSourceFn>;
+
+pub type WithdrawFn = (CallIndex, OracleId);
+pub type WithdrawXt = UncheckedExtrinsicV4<WithdrawFn>;
+
 pub const ORACLE_MODULE: &str = "OracleModule";
 pub const ORACLE_STORAGE: &str = "OracleModule";
 pub const ORACLE_CREATE: &str = "create_oracle";
 pub const ORACLE_CREATED_EVENT: &str = "OracleCreated";
 pub const ORACLE_SEQUENCE: &str = "OracleIdSequence";
 
-trait OracleModule {
+pub const ORACLE_PUSH: &str = "push_value";
+pub const ORACLE_COMMIT: &str = "commit_value";
+pub const ORACLE_REGISTER_SOURCE: &str = "register_source";
+pub const ORACLE_WITHDRAW: &str = "withdraw";
+pub const ORACLE_VALUE_PUSHED_EVENT: &str = "ValuePushed";
+pub const ORACLE_SOURCE_REGISTERED_EVENT: &str = "SourceRegistered";
+
+trait OracleModule<P: Pair> {
+    /// Composes and signs a `create_oracle` call using the `Api`'s own
+    /// signer, an immortal era and a freshly-fetched nonce — a thin default
+    /// over [`OracleModule::create_oracle_with_params`] for the common case.
     fn create_oracle(
         &self,
         name: Vec<u8>,
@@ -54,9 +86,49 @@ trait OracleModule {
         asset_id: AssetId,
         value_names: Vec<Vec<u8>>,
     ) -> CreateOracleXt;
+
+    /// Same call as [`OracleModule::create_oracle`], but with explicit
+    /// [`ExtrinsicParams`] (tip/era/nonce) and signing handled by `sign`
+    /// rather than implicitly by the `Api`'s own signer — e.g. to sign with
+    /// an offline key, or on a runtime whose `SignedExtra` isn't the
+    /// node-primitives default that `compose_extrinsic!` assumes.
+    fn create_oracle_with_params(
+        &self,
+        name: Vec<u8>,
+        source_limit: u8,
+        period: Moment,
+        aggregate_period: Moment,
+        asset_id: AssetId,
+        value_names: Vec<Vec<u8>>,
+        params: ExtrinsicParams,
+        signer: &P,
+        sign: &impl SignExtrinsic<P>,
+    ) -> CreateOracleXt;
+
+    /// Submits a single measurement for `oracle` at `value_names[value_index]`, stamped
+    /// with the `Moment` it was observed. The account composing the extrinsic must
+    /// already be a registered source for `oracle`, see [`OracleModule::register_source`].
+    fn push_value(
+        &self,
+        oracle: OracleId,
+        value_index: u8,
+        value: u128,
+        observed_at: Moment,
+    ) -> PushValueXt;
+
+    /// Closes out the current aggregation window for `oracle`, committing the
+    /// aggregated value on-chain.
+    fn commit_value(&self, oracle: OracleId) -> CommitValueXt;
+
+    /// Registers the signing account as one of `oracle`'s `source_limit` value
+    /// providers, so it may subsequently call [`OracleModule::push_value`].
+    fn register_source(&self, oracle: OracleId) -> RegisterSourceXt;
+
+    /// Withdraws the signing account from `oracle`'s set of registered sources.
+    fn withdraw(&self, oracle: OracleId) -> WithdrawXt;
 }
 
-impl<P> OracleModule for Api<P>
+impl<P> OracleModule<P> for Api<P>
 where
     P: Pair,
     MultiSignature: From<P::Signature>,
@@ -70,8 +142,41 @@ where
         asset_id: AssetId,
         value_names: Vec<Vec<u8>>,
     ) -> CreateOracleXt {
-        compose_extrinsic!(
-            self,
+        let signer = self
+            .signer
+            .as_ref()
+            .expect("create_oracle requires a signer to be set on the Api");
+        let nonce = self
+            .get_nonce()
+            .expect("failed to fetch the signer's account nonce");
+
+        self.create_oracle_with_params(
+            name,
+            source_limit,
+            period,
+            aggregate_period,
+            asset_id,
+            value_names,
+            ExtrinsicParams::immortal(nonce),
+            signer,
+            &PairSigner,
+        )
+    }
+
+    fn create_oracle_with_params(
+        &self,
+        name: Vec<u8>,
+        source_limit: u8,
+        period: Moment,
+        aggregate_period: Moment,
+        asset_id: AssetId,
+        value_names: Vec<Vec<u8>>,
+        params: ExtrinsicParams,
+        signer: &P,
+        sign: &impl SignExtrinsic<P>,
+    ) -> CreateOracleXt {
+        let call = compose_call!(
+            self.metadata,
             ORACLE_MODULE,
             ORACLE_CREATE,
             name,
@@ -80,8 +185,40 @@ where
             aggregate_period,
             asset_id,
             value_names
+        );
+
+        compose_signed_extrinsic(self, call, params, signer, sign)
+    }
+
+    fn push_value(
+        &self,
+        oracle: OracleId,
+        value_index: u8,
+        value: u128,
+        observed_at: Moment,
+    ) -> PushValueXt {
+        compose_extrinsic!(
+            self,
+            ORACLE_MODULE,
+            ORACLE_PUSH,
+            oracle,
+            value_index,
+            value,
+            observed_at
         )
     }
+
+    fn commit_value(&self, oracle: OracleId) -> CommitValueXt {
+        compose_extrinsic!(self, ORACLE_MODULE, ORACLE_COMMIT, oracle)
+    }
+
+    fn register_source(&self, oracle: OracleId) -> RegisterSourceXt {
+        compose_extrinsic!(self, ORACLE_MODULE, ORACLE_REGISTER_SOURCE, oracle)
+    }
+
+    fn withdraw(&self, oracle: OracleId) -> WithdrawXt {
+        compose_extrinsic!(self, ORACLE_MODULE, ORACLE_WITHDRAW, oracle)
+    }
 }
 
 pub fn get_local_test_node() -> String {
@@ -104,12 +241,70 @@ impl std::fmt::Display for OracleCreatedArgs {
     }
 }
 
+#[derive(Decode, Debug)]
+struct OracleValuePushedArgs {
+    oracle: OracleId,
+    source: AccountId,
+    value_index: u8,
+    value: u128,
+}
+
+impl std::fmt::Display for OracleValuePushedArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Oracle id: {}, value[{}] = {} pushed by: {}",
+            self.oracle, self.value_index, self.value, self.source
+        )
+    }
+}
+
+#[derive(Decode, Debug)]
+pub struct OracleSourceRegisteredArgs {
+    oracle: OracleId,
+    source: AccountId,
+}
+
+impl std::fmt::Display for OracleSourceRegisteredArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Oracle id: {}, registered source: {}",
+            self.oracle, self.source
+        )
+    }
+}
+
+/// Error returned by the timeout-bounded `wait_for_*custom_event` variants.
+#[derive(Debug)]
+pub enum WaitError {
+    /// No matching event arrived before the deadline.
+    Timeout,
+    /// The event channel was closed (e.g. the subscription was dropped).
+    Channel(String),
+    /// The event data was received but couldn't be decoded.
+    Decode(String),
+}
+
+impl std::fmt::Display for WaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitError::Timeout => write!(f, "timed out waiting for event"),
+            WaitError::Channel(err) => write!(f, "event channel error: {}", err),
+            WaitError::Decode(err) => write!(f, "event decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
 trait WaitForCustomEvent {
     fn wait_for_custom_event<E: Decode>(
         &self,
         module: &str,
         variant: &str,
         receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
     ) -> Result<E, String>;
 
     fn wait_for_raw_custom_event(
@@ -117,7 +312,29 @@ trait WaitForCustomEvent {
         module: &str,
         variant: &str,
         receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
     ) -> Result<RawEvent, String>;
+
+    /// Like [`WaitForCustomEvent::wait_for_custom_event`], but gives up with
+    /// [`WaitError::Timeout`] instead of blocking forever if no matching event
+    /// arrives within `timeout`.
+    fn wait_for_custom_event_timeout<E: Decode>(
+        &self,
+        module: &str,
+        variant: &str,
+        receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
+        timeout: Duration,
+    ) -> Result<E, WaitError>;
+
+    fn wait_for_raw_custom_event_timeout(
+        &self,
+        module: &str,
+        variant: &str,
+        receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
+        timeout: Duration,
+    ) -> Result<RawEvent, WaitError>;
 }
 
 impl<P> WaitForCustomEvent for Api<P>
@@ -130,8 +347,9 @@ where
         module: &str,
         variant: &str,
         receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
     ) -> Result<E, String> {
-        self.wait_for_raw_custom_event(module, variant, receiver)
+        self.wait_for_raw_custom_event(module, variant, receiver, registry)
             .map(|raw| E::decode(&mut &raw.data[..]).map_err(|err| err.to_string()))
             .flatten()
     }
@@ -141,15 +359,78 @@ where
         module: &str,
         variant: &str,
         receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
     ) -> Result<RawEvent, String> {
+        let mut event_decoder =
+            EventsDecoder::try_from(self.metadata.clone()).map_err(|err| err.to_string())?;
+        registry.apply(&mut event_decoder)?;
+
         loop {
             let unhex = hexstr_to_vec(receiver.recv().map_err(|err| err.to_string())?)
                 .map_err(|err| err.to_string())?;
 
-            let mut event_decoder = EventsDecoder::try_from(self.metadata.clone()).unwrap();
-            event_decoder
-                .register_type_size::<OracleId>("OracleId")
-                .unwrap(); // All DRY-violation (from client code) for this line
+            match event_decoder.decode_events(&mut unhex.as_slice()) {
+                Ok(raw_events) => {
+                    for (_phase, event) in raw_events.into_iter() {
+                        match event {
+                            RuntimeEvent::Raw(raw)
+                                if raw.module == module && raw.variant == variant =>
+                            {
+                                return Ok(raw)
+                            }
+                            _ => log::debug!("ignoring unsupported module event: {:?}", event),
+                        }
+                    }
+                }
+                Err(_) => log::error!("couldn't decode event record list"),
+            }
+        }
+    }
+
+    fn wait_for_custom_event_timeout<E: Decode>(
+        &self,
+        module: &str,
+        variant: &str,
+        receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
+        timeout: Duration,
+    ) -> Result<E, WaitError> {
+        self.wait_for_raw_custom_event_timeout(module, variant, receiver, registry, timeout)
+            .and_then(|raw| {
+                E::decode(&mut &raw.data[..]).map_err(|err| WaitError::Decode(err.to_string()))
+            })
+    }
+
+    fn wait_for_raw_custom_event_timeout(
+        &self,
+        module: &str,
+        variant: &str,
+        receiver: &Receiver<String>,
+        registry: &CustomTypeRegistry,
+        timeout: Duration,
+    ) -> Result<RawEvent, WaitError> {
+        let mut event_decoder = EventsDecoder::try_from(self.metadata.clone())
+            .map_err(|err| WaitError::Decode(err.to_string()))?;
+        registry
+            .apply(&mut event_decoder)
+            .map_err(WaitError::Decode)?;
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(WaitError::Timeout);
+            }
+
+            let hex = match receiver.recv_timeout(remaining) {
+                Ok(hex) => hex,
+                Err(RecvTimeoutError::Timeout) => return Err(WaitError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(WaitError::Channel("sender disconnected".to_owned()))
+                }
+            };
+            let unhex = hexstr_to_vec(hex).map_err(WaitError::Decode)?;
 
             match event_decoder.decode_events(&mut unhex.as_slice()) {
                 Ok(raw_events) => {
@@ -170,6 +451,33 @@ where
     }
 }
 
+/// Spawns `wait_for_custom_event` onto a blocking task so it can be awaited
+/// from an async context without stalling the executor. Errors are logged
+/// rather than propagated, since a detached task has no caller to report them
+/// to; the returned `JoinHandle` yields `None` in that case.
+pub fn spawn_wait_for_custom_event<P, E>(
+    api: Arc<Api<P>>,
+    module: &'static str,
+    variant: &'static str,
+    receiver: Receiver<String>,
+    registry: Arc<CustomTypeRegistry>,
+) -> tokio::task::JoinHandle<Option<E>>
+where
+    P: Pair + Send + Sync + 'static,
+    MultiSignature: From<P::Signature>,
+    E: Decode + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        match api.wait_for_custom_event::<E>(module, variant, &receiver, &registry) {
+            Ok(event) => Some(event),
+            Err(err) => {
+                log::error!("wait_for_custom_event task failed: {}", err);
+                None
+            }
+        }
+    })
+}
+
 fn main() {
     let _ = env_logger::builder()
         .filter_level(log::LevelFilter::Trace)
@@ -197,7 +505,7 @@ fn main() {
             5,                              // source_limit
             10,                             // period
             5,                              // aggregate_period
-            1,                              // asset_id
+            AssetId(1),                     // asset_id
             vec!["USD/RUB", "EUR/USD"]
                 .into_iter()
                 .map(|s| s.to_owned().into_bytes())
@@ -207,11 +515,43 @@ fn main() {
 
     assert!(api.send_extrinsic(xt).is_ok());
 
-    let args: Result<OracleCreatedArgs, String> =
-        api.wait_for_custom_event(ORACLE_MODULE, ORACLE_CREATED_EVENT, &events_out);
+    let event_types = CustomTypeRegistry::new().register::<OracleId>("OracleId");
+    let args: Result<OracleCreatedArgs, String> = api.wait_for_custom_event(
+        ORACLE_MODULE,
+        ORACLE_CREATED_EVENT,
+        &events_out,
+        &event_types,
+    );
 
     match args {
-        Ok(event) => println!("{}!", event),
+        Ok(event) => {
+            println!("{}!", event);
+
+            let api = std::sync::Arc::new(api);
+            let feeds = sources::spawn_feeds(
+                api,
+                event.oracle,
+                vec![
+                    sources::PriceSourceConfig {
+                        url: "https://api.example.com/ticker/USDRUB".to_owned(),
+                        json_pointer: "/price".to_owned(),
+                        value_index: 0,
+                        price_scale: 1_000_000,
+                    },
+                    sources::PriceSourceConfig {
+                        url: "https://api.example.com/ticker/EURUSD".to_owned(),
+                        json_pointer: "/price".to_owned(),
+                        value_index: 1,
+                        price_scale: 1_000_000,
+                    },
+                ],
+                &events_out,
+            );
+
+            for feed in feeds {
+                let _ = feed.join();
+            }
+        }
         Err(err) => println!("Oracle event decode failed with error {}", err),
     };
 }
@@ -0,0 +1,45 @@
+//! Configurable registry of custom-sized event fields for [`EventsDecoder`].
+//!
+//! The node metadata alone isn't enough for `EventsDecoder` to know the SCALE
+//! length of certain custom types used as event arguments; each has to be
+//! registered by name before decoding. `CustomTypeRegistry` lets a caller build
+//! that set of registrations once and hand it to the wait/subscribe calls,
+//! instead of the decoder hardcoding a single type as it used to.
+
+use codec::Decode;
+use substrate_api_client::events::EventsDecoder;
+
+type Registration = Box<dyn Fn(&mut EventsDecoder) -> Result<usize, String>>;
+
+/// Builder for the set of custom types an [`EventsDecoder`] needs to know the
+/// encoded size of. Populate it once with [`CustomTypeRegistry::register`] and
+/// pass it into the `wait_for_*custom_event` calls.
+#[derive(Default)]
+pub struct CustomTypeRegistry {
+    registrations: Vec<Registration>,
+}
+
+impl CustomTypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` under `name`, so any event whose metadata names this type
+    /// as an argument can be sized and decoded correctly.
+    pub fn register<T: Decode + Default + 'static>(mut self, name: &'static str) -> Self {
+        self.registrations.push(Box::new(move |decoder| {
+            decoder
+                .register_type_size::<T>(name)
+                .map_err(|err| err.to_string())
+        }));
+        self
+    }
+
+    /// Applies every registration to `decoder`, in the order they were added.
+    pub(crate) fn apply(&self, decoder: &mut EventsDecoder) -> Result<(), String> {
+        for registration in &self.registrations {
+            registration(decoder)?;
+        }
+        Ok(())
+    }
+}
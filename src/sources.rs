@@ -0,0 +1,215 @@
+//! Exchange price-source connectors: poll external HTTP price endpoints and
+//! submit the quotes they report through the oracle's `push_value` extrinsic.
+
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+use sp_core::crypto::Pair;
+use sp_runtime::MultiSignature;
+use substrate_api_client::Api;
+
+use crate::registry::CustomTypeRegistry;
+use crate::scheduler;
+use crate::{
+    Moment, OracleId, OracleModule, OracleSourceRegisteredArgs, WaitForCustomEvent, ORACLE_MODULE,
+    ORACLE_SOURCE_REGISTERED_EVENT,
+};
+
+/// How long [`spawn_feeds`] waits for the chain to confirm `register_source`
+/// before giving up and refusing to start feeding.
+const REGISTER_SOURCE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Describes a single external price feed: where to fetch it, how to pick the
+/// quoted value out of the response body, and which of the oracle's
+/// `value_names` slot it feeds.
+#[derive(Clone, Debug)]
+pub struct PriceSourceConfig {
+    /// HTTP endpoint returning a JSON document containing the quoted price.
+    pub url: String,
+    /// [RFC 6901](https://tools.ietf.org/html/rfc6901) JSON pointer locating the
+    /// price within the response body, e.g. `/price` or `/data/0/last`.
+    pub json_pointer: String,
+    /// Index into the oracle's `value_names` this feed is responsible for.
+    pub value_index: u8,
+    /// Fixed-point scale applied to the quoted decimal price before it's cast
+    /// to the `u128` the chain expects, e.g. `1_000_000` to keep 6 decimal
+    /// digits of a `"75.43"`-style quote.
+    pub price_scale: u128,
+}
+
+/// Registers the `Api`'s signer as one of `oracle`'s sources and waits for
+/// the chain to confirm it, then spawns one thread per entry in `sources`.
+/// Each thread waits for `oracle`'s aggregation window to open (see
+/// [`scheduler::run_aligned`]), then fetches its endpoint and pushes the
+/// quoted price via [`OracleModule::push_value`]. Returns the join handles so
+/// the caller can wait on (or abandon) the feeder threads.
+///
+/// `push_value` is only accepted from a registered source (see
+/// [`OracleModule::register_source`]), so `events` must be the receiver end
+/// of the same event subscription the caller used to create `oracle` — if
+/// registration never confirms, no feeds are started and an empty `Vec` is
+/// returned.
+pub fn spawn_feeds<P>(
+    api: Arc<Api<P>>,
+    oracle: OracleId,
+    sources: Vec<PriceSourceConfig>,
+    events: &Receiver<String>,
+) -> Vec<thread::JoinHandle<()>>
+where
+    P: Pair + Send + Sync + 'static,
+    MultiSignature: From<P::Signature>,
+{
+    let xt = api.register_source(oracle).hex_encode();
+    if let Err(err) = api.send_extrinsic(xt) {
+        log::error!(
+            "failed to submit register_source for oracle {}: {:?}",
+            oracle,
+            err
+        );
+        return Vec::new();
+    }
+
+    let registry = CustomTypeRegistry::new().register::<OracleId>("OracleId");
+    let confirmed: Result<OracleSourceRegisteredArgs, _> = api.wait_for_custom_event_timeout(
+        ORACLE_MODULE,
+        ORACLE_SOURCE_REGISTERED_EVENT,
+        events,
+        &registry,
+        REGISTER_SOURCE_TIMEOUT,
+    );
+
+    if let Err(err) = confirmed {
+        log::error!(
+            "register_source for oracle {} was never confirmed: {}",
+            oracle,
+            err
+        );
+        return Vec::new();
+    }
+
+    sources
+        .into_iter()
+        .map(|source| {
+            let api = api.clone();
+            thread::spawn(move || run_feed(api, oracle, source))
+        })
+        .collect()
+}
+
+fn run_feed<P>(api: Arc<Api<P>>, oracle: OracleId, source: PriceSourceConfig)
+where
+    P: Pair,
+    MultiSignature: From<P::Signature>,
+{
+    scheduler::run_aligned(&api, oracle, || match fetch_price(&source) {
+        Ok(value) => {
+            let observed_at = now_as_moment();
+            let xt = api
+                .push_value(oracle, source.value_index, value, observed_at)
+                .hex_encode();
+
+            match api.send_extrinsic(xt) {
+                Ok(_) => true,
+                Err(err) => {
+                    log::error!(
+                        "failed to submit value[{}] for oracle {}: {:?}",
+                        source.value_index,
+                        oracle,
+                        err
+                    );
+                    false
+                }
+            }
+        }
+        Err(err) => {
+            log::warn!(
+                "fetch failed for {} ({}): {}",
+                source.url,
+                source.json_pointer,
+                err
+            );
+            false
+        }
+    })
+}
+
+fn fetch_price(source: &PriceSourceConfig) -> Result<u128, String> {
+    let body: Value = reqwest::blocking::get(&source.url)
+        .map_err(|err| err.to_string())?
+        .json()
+        .map_err(|err| err.to_string())?;
+
+    let quoted = body
+        .pointer(&source.json_pointer)
+        .ok_or_else(|| format!("json pointer {} not found in response", source.json_pointer))?;
+
+    scale_quoted_price(quoted, source.price_scale)
+        .map_err(|err| format!("value at {}: {}", source.json_pointer, err))
+}
+
+/// Parses a JSON value quoting a price (either a decimal string like
+/// `"75.43"` or a bare JSON number) and scales it to a fixed-point `u128`,
+/// e.g. `price_scale: 1_000_000` keeps 6 decimal digits of precision.
+/// Pulled out of [`fetch_price`] so the parsing/scaling math can be unit
+/// tested without doing any HTTP I/O.
+fn scale_quoted_price(quoted: &Value, price_scale: u128) -> Result<u128, String> {
+    // Tickers quote decimals ("75.43" or 75.43), not bare integers, so parse
+    // as a float and scale to a fixed-point u128 rather than assuming the
+    // upstream API hands back something `u128`-shaped already.
+    let price = quoted
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .or_else(|| quoted.as_f64())
+        .ok_or_else(|| "was not a parseable price".to_owned())?;
+
+    if !price.is_finite() || price < 0.0 {
+        return Err(format!("was not a valid price: {}", price));
+    }
+
+    Ok((price * price_scale as f64).round() as u128)
+}
+
+fn now_as_moment() -> Moment {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before the unix epoch")
+        .as_millis() as Moment
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn scales_a_quoted_decimal_string() {
+        assert_eq!(
+            scale_quoted_price(&json!("75.43"), 1_000_000),
+            Ok(75_430_000)
+        );
+    }
+
+    #[test]
+    fn scales_a_quoted_decimal_number() {
+        assert_eq!(scale_quoted_price(&json!(75.43), 1_000_000), Ok(75_430_000));
+    }
+
+    #[test]
+    fn scales_a_bare_integer() {
+        assert_eq!(scale_quoted_price(&json!(75), 1), Ok(75));
+    }
+
+    #[test]
+    fn rejects_a_negative_price() {
+        assert!(scale_quoted_price(&json!(-1.0), 1_000_000).is_err());
+    }
+
+    #[test]
+    fn rejects_a_non_numeric_value() {
+        assert!(scale_quoted_price(&json!("not a price"), 1_000_000).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Strongly-typed identifiers.
+//!
+//! `OracleId` and `AssetId` used to be bare `u32` type aliases, which meant
+//! the compiler couldn't stop an `AssetId` from being passed where an
+//! `OracleId` was expected. These newtypes keep the exact same SCALE encoding
+//! as the primitive they wrap (so they round-trip through the runtime
+//! unchanged) while adding `Display`/`FromStr` so ids can be printed and
+//! persisted (e.g. a created oracle's id saved to disk) across runs.
+//!
+//! `CallIndex` stays a structural `[u8; 2]` alias rather than joining them:
+//! `compose_extrinsic!`/`compose_call!` synthesize the call index themselves
+//! as a bare array literal, and a nominal newtype there wouldn't coerce to
+//! what the macros actually produce.
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use codec::{Decode, Encode};
+
+pub type CallIndex = [u8; 2];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub struct OracleId(pub u32);
+
+impl fmt::Display for OracleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for OracleId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u32>().map(OracleId)
+    }
+}
+
+impl From<u32> for OracleId {
+    fn from(id: u32) -> Self {
+        OracleId(id)
+    }
+}
+
+impl From<OracleId> for u32 {
+    fn from(id: OracleId) -> Self {
+        id.0
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub struct AssetId(pub u32);
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for AssetId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u32>().map(AssetId)
+    }
+}
+
+impl From<u32> for AssetId {
+    fn from(id: u32) -> Self {
+        AssetId(id)
+    }
+}
+
+impl From<AssetId> for u32 {
+    fn from(id: AssetId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oracle_id_round_trips_through_its_string_representation() {
+        let id = OracleId(42);
+        assert_eq!(id.to_string().parse::<OracleId>().unwrap(), id);
+    }
+
+    #[test]
+    fn asset_id_round_trips_through_its_string_representation() {
+        let id = AssetId(42);
+        assert_eq!(id.to_string().parse::<AssetId>().unwrap(), id);
+    }
+
+    #[test]
+    fn oracle_id_from_str_rejects_non_numeric_input() {
+        assert!("not a number".parse::<OracleId>().is_err());
+    }
+}